@@ -10,6 +10,8 @@ use http;
 use hyper;
 #[cfg(feature = "openssl")]
 use openssl;
+#[cfg(feature = "rustls")]
+use rustls;
 use response;
 
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -30,6 +32,11 @@ pub enum ErrorKind {
     Base64,
     #[fail(display = "response error")]
     Response,
+    #[fail(display = "docker daemon returned {}: {}", code, message)]
+    Fault {
+        code: http::StatusCode,
+        message: String,
+    },
     #[fail(display = "http error")]
     Http,
     #[fail(display = "http uri invalid error")]
@@ -44,6 +51,23 @@ pub enum ErrorKind {
     HyperTlsError,
     #[fail(display = "openssl error")]
     OpenSSL,
+    #[fail(display = "rustls error")]
+    Rustls,
+    #[fail(
+        display = "certificate transparency check failed: found {} valid SCT(s), needed {}",
+        found,
+        needed
+    )]
+    CertificateTransparency { found: usize, needed: usize },
+    #[fail(display = "malformed WWW-Authenticate challenge: '{}'", header)]
+    InvalidAuthChallenge { header: String },
+    #[fail(display = "registry token exchange with '{}' failed", realm)]
+    TokenExchangeFailed { realm: String },
+    #[fail(
+        display = "Docker daemon did not upgrade the connection (got status {})",
+        status
+    )]
+    ConnectionNotUpgraded { status: http::StatusCode },
     #[fail(display = "could not fetch information about container '{}'", id)]
     ContainerInfo { id: String },
     #[fail(display = "could not connected to Docker at '{}'", host)]
@@ -95,6 +119,24 @@ impl Error {
     }
 }
 
+impl ErrorKind {
+    /// Builds a `Fault` from a non-2xx daemon response, attempting to parse
+    /// the standard `{"message": "..."}` error JSON and falling back to the
+    /// raw body when the response isn't valid JSON.
+    pub fn fault(code: http::StatusCode, body: &[u8]) -> ErrorKind {
+        let message = ::serde_json::from_slice::<::serde_json::Value>(body)
+            .ok()
+            .and_then(|value| {
+                value
+                    .get("message")
+                    .and_then(|message| message.as_str())
+                    .map(String::from)
+            }).unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+
+        ErrorKind::Fault { code, message }
+    }
+}
+
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Error {
         Error {
@@ -222,3 +264,58 @@ impl From<openssl::error::ErrorStack> for Error {
         }
     }
 }
+
+#[cfg(feature = "rustls")]
+impl From<rustls::TLSError> for Error {
+    fn from(error: rustls::TLSError) -> Self {
+        Error {
+            inner: error.context(ErrorKind::Rustls),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fault_reads_the_message_field_from_a_daemon_error_body() {
+        let kind = ErrorKind::fault(
+            http::StatusCode::NOT_FOUND,
+            br#"{"message": "no such container: abc"}"#,
+        );
+
+        match kind {
+            ErrorKind::Fault { code, message } => {
+                assert_eq!(code, http::StatusCode::NOT_FOUND);
+                assert_eq!(message, "no such container: abc");
+            }
+            other => panic!("expected ErrorKind::Fault, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fault_falls_back_to_the_raw_body_when_it_is_not_json() {
+        let kind = ErrorKind::fault(http::StatusCode::INTERNAL_SERVER_ERROR, b"internal error");
+
+        match kind {
+            ErrorKind::Fault { code, message } => {
+                assert_eq!(code, http::StatusCode::INTERNAL_SERVER_ERROR);
+                assert_eq!(message, "internal error");
+            }
+            other => panic!("expected ErrorKind::Fault, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fault_falls_back_to_the_raw_body_when_json_has_no_message() {
+        let kind = ErrorKind::fault(http::StatusCode::CONFLICT, br#"{"other": "field"}"#);
+
+        match kind {
+            ErrorKind::Fault { message, .. } => {
+                assert_eq!(message, r#"{"other": "field"}"#);
+            }
+            other => panic!("expected ErrorKind::Fault, got {:?}", other),
+        }
+    }
+}
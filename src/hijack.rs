@@ -0,0 +1,55 @@
+//! Connection hijacking for interactive `attach`/`exec`, where the daemon
+//! upgrades the HTTP connection to a raw bidirectional stream instead of
+//! returning a normal response.
+
+use http::StatusCode;
+use hyper::client::connect::Connect;
+use hyper::upgrade::Upgraded;
+use hyper::{Body, Client, Request};
+
+use errors::{Error, ErrorKind, Result};
+
+/// Sends `request` with `Connection: Upgrade`/`Upgrade: tcp` and, if the
+/// daemon replies with `101 Switching Protocols`, returns the hijacked
+/// duplex stream. Any other status is surfaced as
+/// `ErrorKind::ConnectionNotUpgraded` rather than treated as a normal
+/// response body.
+pub fn hijack<C>(client: &Client<C>, mut request: Request<Body>) -> Result<HijackedStream>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    request
+        .headers_mut()
+        .insert(::http::header::CONNECTION, "Upgrade".parse().unwrap());
+    request
+        .headers_mut()
+        .insert(::http::header::UPGRADE, "tcp".parse().unwrap());
+
+    let response = ::tokio::runtime::current_thread::block_on_all(client.request(request))
+        .map_err(Error::from)?;
+
+    if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(ErrorKind::ConnectionNotUpgraded {
+            status: response.status(),
+        }.into());
+    }
+
+    let upgraded = ::tokio::runtime::current_thread::block_on_all(
+        ::hyper::upgrade::on(response),
+    ).map_err(Error::from)?;
+
+    Ok(HijackedStream { upgraded })
+}
+
+/// A bidirectional raw stream hijacked from an upgraded HTTP connection,
+/// used to drive an interactive `attach`/`exec` session.
+pub struct HijackedStream {
+    upgraded: Upgraded,
+}
+
+impl HijackedStream {
+    /// Splits the stream into independent read and write halves.
+    pub fn split(self) -> (::tokio::io::ReadHalf<Upgraded>, ::tokio::io::WriteHalf<Upgraded>) {
+        ::tokio::io::AsyncRead::split(self.upgraded)
+    }
+}
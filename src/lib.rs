@@ -0,0 +1,41 @@
+extern crate base64;
+extern crate docker;
+extern crate failure;
+#[macro_use]
+extern crate failure_derive;
+extern crate futures;
+extern crate http;
+extern crate hyper;
+#[cfg(feature = "openssl")]
+extern crate hyper_tls;
+#[cfg(feature = "rustls")]
+extern crate hyper_rustls;
+extern crate mime;
+#[cfg(feature = "openssl")]
+extern crate openssl;
+#[cfg(feature = "rustls")]
+extern crate rustls;
+#[cfg(feature = "rustls")]
+extern crate rustls_native_certs;
+#[cfg(feature = "rustls")]
+extern crate webpki_roots;
+#[cfg(feature = "ct_logs")]
+extern crate sct;
+#[cfg(feature = "ct_logs")]
+extern crate webpki;
+extern crate response;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate tokio;
+extern crate url;
+
+pub mod errors;
+pub mod hijack;
+pub mod registry_auth;
+
+#[cfg(feature = "rustls")]
+pub mod tls_rustls;
+
+#[cfg(feature = "ct_logs")]
+pub mod ct_logs;
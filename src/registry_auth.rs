@@ -0,0 +1,254 @@
+//! Authentication against private Docker registries: the `X-Registry-Auth`
+//! header expected by the daemon on push/pull, and the standalone Docker
+//! Registry v2 bearer token handshake used when talking to a registry
+//! directly.
+
+use base64;
+use futures::Stream;
+use http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use http::StatusCode;
+use hyper::client::connect::Connect;
+use hyper::{Body, Client, Request, Response};
+
+use errors::{Error, ErrorKind, Result};
+
+/// Credentials for a single registry, as accepted by the daemon's
+/// `X-Registry-Auth` header on image push/pull/build requests.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AuthConfig {
+    pub username: String,
+    pub password: String,
+    pub email: String,
+    pub serveraddress: String,
+}
+
+impl AuthConfig {
+    /// Base64url-encodes this config as the daemon expects it in the
+    /// `X-Registry-Auth` header.
+    pub fn to_header_value(&self) -> Result<HeaderValue> {
+        let json = ::serde_json::to_vec(self)?;
+        let encoded = base64::encode_config(&json, base64::URL_SAFE);
+        HeaderValue::from_str(&encoded).map_err(|_| Error::from(ErrorKind::Base64))
+    }
+}
+
+/// A parsed `WWW-Authenticate: Bearer realm="…",service="…",scope="…"`
+/// challenge, as returned by a registry on a 401.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BearerChallenge {
+    pub realm: String,
+    pub service: Option<String>,
+    pub scope: Option<String>,
+}
+
+impl BearerChallenge {
+    /// Parses a `WWW-Authenticate` header value, returning
+    /// `InvalidAuthChallenge` when it isn't a `Bearer` challenge carrying
+    /// at least a `realm`.
+    pub fn parse(header: &str) -> Result<BearerChallenge> {
+        let header = header.trim();
+        if !header.starts_with("Bearer ") {
+            return Err(ErrorKind::InvalidAuthChallenge {
+                header: header.to_owned(),
+            }.into());
+        }
+        let rest = &header["Bearer ".len()..];
+
+        let mut realm = None;
+        let mut service = None;
+        let mut scope = None;
+
+        for part in rest.split(',') {
+            let mut kv = part.splitn(2, '=');
+            let key = kv.next().unwrap_or("").trim();
+            let value = kv
+                .next()
+                .map(|value| value.trim().trim_matches('"').to_owned());
+
+            match key {
+                "realm" => realm = value,
+                "service" => service = value,
+                "scope" => scope = value,
+                _ => {}
+            }
+        }
+
+        realm
+            .map(|realm| BearerChallenge {
+                realm,
+                service,
+                scope,
+            }).ok_or_else(|| {
+                ErrorKind::InvalidAuthChallenge {
+                    header: header.to_owned(),
+                }.into()
+            })
+    }
+}
+
+/// Sends a request built from `builder`/`body` and, if the registry
+/// challenges it with a 401 carrying a `WWW-Authenticate: Bearer` header,
+/// exchanges that challenge for a token and retries the same request with
+/// `Authorization: Bearer <token>` added. `builder` is called again for
+/// the retry, since a `hyper::Request` can't be cloned once built. Any
+/// other non-2xx response (including a failed retry) is surfaced as
+/// `ErrorKind::fault`.
+pub fn request_with_auth_retry<C, F>(
+    client: &Client<C>,
+    mut builder: F,
+    body: &[u8],
+    auth: Option<&AuthConfig>,
+) -> Result<Response<Body>>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+    F: FnMut() -> ::http::request::Builder,
+{
+    let request = builder().body(Body::from(body.to_vec())).map_err(Error::from)?;
+    let response = ::tokio::runtime::current_thread::block_on_all(client.request(request))
+        .map_err(Error::from)?;
+
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return ensure_success(response);
+    }
+
+    let challenge = response
+        .headers()
+        .get(WWW_AUTHENTICATE)
+        .and_then(|header| header.to_str().ok())
+        .ok_or_else(|| {
+            ErrorKind::InvalidAuthChallenge {
+                header: String::new(),
+            }
+        })?;
+    let challenge = BearerChallenge::parse(challenge)?;
+
+    let token = exchange_token(client, &challenge, auth)?;
+
+    let retry = builder()
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::from(body.to_vec()))
+        .map_err(Error::from)?;
+
+    let response = ::tokio::runtime::current_thread::block_on_all(client.request(retry))
+        .map_err(Error::from)?;
+
+    ensure_success(response)
+}
+
+fn ensure_success(response: Response<Body>) -> Result<Response<Body>> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = ::tokio::runtime::current_thread::block_on_all(response.into_body().concat2())
+        .map_err(Error::from)?;
+    Err(ErrorKind::fault(status, &body).into())
+}
+
+/// Exchanges a `BearerChallenge` for a token, issuing a `GET` to `realm`
+/// with `service`/`scope` query params and, when credentials are
+/// available, HTTP Basic auth, then parsing the `{"token": "…"}` response.
+pub fn exchange_token<C>(
+    client: &Client<C>,
+    challenge: &BearerChallenge,
+    auth: Option<&AuthConfig>,
+) -> Result<String>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut url = ::url::Url::parse(&challenge.realm).map_err(|_| {
+        ErrorKind::TokenExchangeFailed {
+            realm: challenge.realm.clone(),
+        }
+    })?;
+
+    {
+        let mut query = url.query_pairs_mut();
+        if let Some(ref service) = challenge.service {
+            query.append_pair("service", service);
+        }
+        if let Some(ref scope) = challenge.scope {
+            query.append_pair("scope", scope);
+        }
+    }
+
+    let mut request = Request::get(url.as_str());
+    if let Some(auth) = auth {
+        let credentials = base64::encode(&format!("{}:{}", auth.username, auth.password));
+        request.header(AUTHORIZATION, format!("Basic {}", credentials));
+    }
+    let request = request.body(Body::empty()).map_err(Error::from)?;
+
+    let response = ::tokio::runtime::current_thread::block_on_all(client.request(request))
+        .map_err(|_| ErrorKind::TokenExchangeFailed {
+            realm: challenge.realm.clone(),
+        })?;
+
+    parse_token_response(response, &challenge.realm)
+}
+
+fn parse_token_response(response: Response<Body>, realm: &str) -> Result<String> {
+    if !response.status().is_success() {
+        return Err(ErrorKind::TokenExchangeFailed {
+            realm: realm.to_owned(),
+        }.into());
+    }
+
+    let body = ::tokio::runtime::current_thread::block_on_all(response.into_body().concat2())
+        .map_err(|_| ErrorKind::TokenExchangeFailed {
+            realm: realm.to_owned(),
+        })?;
+
+    ::serde_json::from_slice::<::serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("token")
+                .and_then(|token| token.as_str())
+                .map(String::from)
+        }).ok_or_else(|| {
+            ErrorKind::TokenExchangeFailed {
+                realm: realm.to_owned(),
+            }.into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_full_bearer_challenge() {
+        let challenge = BearerChallenge::parse(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/ubuntu:pull""#,
+        ).unwrap();
+
+        assert_eq!(challenge.realm, "https://auth.docker.io/token");
+        assert_eq!(challenge.service.as_ref().map(String::as_str), Some("registry.docker.io"));
+        assert_eq!(
+            challenge.scope.as_ref().map(String::as_str),
+            Some("repository:library/ubuntu:pull")
+        );
+    }
+
+    #[test]
+    fn parses_a_challenge_without_scope() {
+        let challenge =
+            BearerChallenge::parse(r#"Bearer realm="https://example.com/token",service="example""#)
+                .unwrap();
+
+        assert_eq!(challenge.realm, "https://example.com/token");
+        assert_eq!(challenge.scope, None);
+    }
+
+    #[test]
+    fn rejects_a_non_bearer_challenge() {
+        assert!(BearerChallenge::parse(r#"Basic realm="example""#).is_err());
+    }
+
+    #[test]
+    fn rejects_a_bearer_challenge_missing_realm() {
+        assert!(BearerChallenge::parse(r#"Bearer service="example""#).is_err());
+    }
+}